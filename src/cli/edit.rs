@@ -2,10 +2,11 @@ use anyhow::{Context, Result};
 use std::process::Command;
 
 use crate::config::Project;
+use crate::git;
 use crate::ui;
 
 pub fn run(project_name: Option<String>) -> Result<()> {
-    let name = match project_name {
+    let name = match project_name.or_else(git::repo_name_from_cwd) {
         Some(n) => n,
         None => ui::select_project("Select project to edit...")?
             .ok_or_else(|| anyhow::anyhow!("No project selected"))?,
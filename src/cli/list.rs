@@ -0,0 +1,57 @@
+use anyhow::{Context, Result};
+use std::fs;
+
+use crate::config::Project;
+
+pub fn run(filter: Option<String>, quiet: bool) -> Result<()> {
+    let mut names = list_project_names()?;
+
+    if let Some(substr) = &filter {
+        names.retain(|name| name.contains(substr.as_str()));
+    }
+
+    if quiet {
+        for name in &names {
+            println!("{}", name);
+        }
+        return Ok(());
+    }
+
+    if names.is_empty() {
+        println!("No projects found.");
+        return Ok(());
+    }
+
+    for name in &names {
+        println!("{}", name);
+    }
+
+    Ok(())
+}
+
+/// Enumerate project names from the same config directory `Project::config_path` uses.
+fn list_project_names() -> Result<Vec<String>> {
+    let config_dir = Project::config_path("")?
+        .parent()
+        .ok_or_else(|| anyhow::anyhow!("Could not determine project config directory"))?
+        .to_path_buf();
+
+    if !config_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut names: Vec<String> = fs::read_dir(&config_dir)
+        .with_context(|| format!("Failed to read config directory: {:?}", config_dir))?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_file())
+        .filter_map(|entry| {
+            entry
+                .path()
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+        })
+        .collect();
+
+    names.sort();
+    Ok(names)
+}
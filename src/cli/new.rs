@@ -2,13 +2,14 @@ use anyhow::{Context, Result};
 use std::fs;
 
 use crate::config::{GlobalConfig, Project};
+use crate::git;
 use crate::gum;
 
 pub fn run(name: Option<String>) -> Result<()> {
     GlobalConfig::ensure_dirs()?;
 
     // Get project name or repo URL
-    let input = match name {
+    let input = match name.or_else(git::repo_name_from_cwd) {
         Some(n) => n,
         None => match gum::input("Project name or repo URL", None)? {
             Some(n) if !n.is_empty() => n,
@@ -3,11 +3,41 @@ use std::fs;
 use std::io::{BufRead, BufReader};
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
-use std::sync::mpsc::{self, Receiver};
+use std::sync::mpsc::{self, Receiver, Sender};
 use std::thread;
 
 use crate::config::{GlobalConfig, Project};
 
+/// Walk up from `start` looking for a `.git` entry, returning the repository root.
+pub fn find_repo_root(start: &Path) -> Option<PathBuf> {
+    let mut dir = start.to_path_buf();
+    loop {
+        if dir.join(".git").exists() {
+            return Some(dir);
+        }
+        if !dir.pop() {
+            return None;
+        }
+    }
+}
+
+/// Infer a project name from the enclosing git repository, for commands run
+/// with no explicit name from inside a checkout.
+///
+/// `TWIG_REPO_NAME` overrides the repository root's directory name, for repos
+/// checked out under a name that doesn't match the project.
+pub fn repo_name_from_cwd() -> Option<String> {
+    if let Ok(name) = std::env::var("TWIG_REPO_NAME") {
+        if !name.is_empty() {
+            return Some(name);
+        }
+    }
+
+    let cwd = std::env::current_dir().ok()?;
+    let root = find_repo_root(&cwd)?;
+    root.file_name().map(|n| n.to_string_lossy().to_string())
+}
+
 /// Message types for streaming command output
 #[derive(Debug, Clone)]
 pub enum CommandOutput {
@@ -133,8 +163,17 @@ impl CommandRunner {
     }
 }
 
-/// Create a git worktree for a project
-pub fn create_worktree(project: &Project, branch: &str) -> Result<PathBuf> {
+/// Create a git worktree for a project.
+///
+/// If `progress` is given, transfer statistics from an optional pre-create
+/// fetch (see `Project.worktree.fetch_before_create`) are streamed to it as
+/// `CommandOutput::Line` messages, the same way `CommandRunner` reports
+/// post-create command output.
+pub fn create_worktree(
+    project: &Project,
+    branch: &str,
+    progress: Option<&Sender<CommandOutput>>,
+) -> Result<PathBuf> {
     let config = GlobalConfig::load()?;
     let project_root = project.root_expanded();
 
@@ -156,6 +195,18 @@ pub fn create_worktree(project: &Project, branch: &str) -> Result<PathBuf> {
             .with_context(|| format!("Failed to create directory: {:?}", parent))?;
     }
 
+    // Make sure a branch that only exists on the remote is visible locally
+    // before we check for it.
+    let fetch_before_create = project
+        .worktree
+        .as_ref()
+        .map(|w| w.fetch_before_create)
+        .unwrap_or(false);
+    if fetch_before_create {
+        use crate::git_backend::GitBackend;
+        crate::git_backend::CliBackend.fetch_origin(&project_root, progress)?;
+    }
+
     // Check if branch exists locally or remotely
     let branch_exists = check_branch_exists(&project_root, branch)?;
 
@@ -183,39 +234,73 @@ pub fn create_worktree(project: &Project, branch: &str) -> Result<PathBuf> {
         anyhow::bail!("git worktree add failed: {}", stderr.trim());
     }
 
-    // Copy files if configured
-    if let Some(wt_config) = &project.worktree {
-        for file in &wt_config.copy {
-            let src = project_root.join(file);
-            let dst = worktree_path.join(file);
-
-            if src.exists() {
-                // Create parent directories if needed
-                if let Some(parent) = dst.parent() {
-                    fs::create_dir_all(parent).ok();
-                }
-
-                copy_path_preserve_symlinks(&src, &dst)?;
-            }
+    // A newly created branch has no upstream yet; point it at the project's
+    // configured remote/prefix so the first push just works.
+    if !branch_exists {
+        if let Some(track) = &project.track {
+            set_upstream(&project_root, branch, track);
         }
+    }
 
-        for file in &wt_config.symlink {
-            let src = project_root.join(file);
-            let dst = worktree_path.join(file);
-
-            if src.exists() {
-                if let Some(parent) = dst.parent() {
-                    fs::create_dir_all(parent).ok();
-                }
+    // Copy/symlink the project's configured worktree files into place
+    crate::worktree::provision(project, &worktree_path)?;
 
-                create_symlink(&src, &dst)?;
-            }
-        }
+    if let Err(err) = crate::oplog::record_operation(&crate::oplog::Operation::WorktreeCreated {
+        project: project.name.clone(),
+        branch: branch.to_string(),
+        path: worktree_path.clone(),
+        branch_created: !branch_exists,
+    }) {
+        eprintln!("Warning: failed to record operation log entry: {}", err);
     }
 
     Ok(worktree_path)
 }
 
+/// Point a freshly created branch at its configured upstream
+/// (`<default_remote>/<default_remote_prefix><branch>`), so teams standardize
+/// where new branches push.
+///
+/// `git branch --set-upstream-to` requires the remote-tracking ref to already
+/// exist, which it never does for a branch nobody has pushed yet. So instead
+/// we set `branch.<name>.remote`/`branch.<name>.merge` directly via `git
+/// config` — the same config `--set-upstream-to` would write — which takes
+/// effect immediately and is honored by the first `push` regardless of
+/// whether the remote has heard of the branch.
+fn set_upstream(repo_path: &Path, branch: &str, track: &crate::config::TrackingConfig) {
+    let upstream_branch = format!("{}{}", track.default_remote_prefix, branch);
+
+    let remote_status = Command::new("git")
+        .current_dir(repo_path)
+        .args([
+            "config",
+            &format!("branch.{}.remote", branch),
+            &track.default_remote,
+        ])
+        .output();
+
+    let merge_status = Command::new("git")
+        .current_dir(repo_path)
+        .args([
+            "config",
+            &format!("branch.{}.merge", branch),
+            &format!("refs/heads/{}", upstream_branch),
+        ])
+        .output();
+
+    for status in [remote_status, merge_status] {
+        match status {
+            Ok(output) if output.status.success() => {}
+            Ok(output) => eprintln!(
+                "Warning: could not configure upstream for '{}': {}",
+                branch,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            Err(err) => eprintln!("Warning: could not configure upstream for '{}': {}", branch, err),
+        }
+    }
+}
+
 /// Get post-create commands for a project (if any)
 pub fn get_post_create_commands(project: &Project) -> Vec<String> {
     project
@@ -259,9 +344,48 @@ pub fn run_post_create_commands(project: &Project, worktree_path: &Path) -> Resu
     Ok(())
 }
 
-/// Delete a git worktree and its local branch
-pub fn delete_worktree(project: &Project, branch: &str) -> Result<()> {
-    let config = GlobalConfig::load()?;
+/// Why a worktree removal was refused.
+#[derive(Debug)]
+pub enum WorktreeRemoveError {
+    /// The worktree has uncommitted changes.
+    Changes,
+    /// The branch has commits that aren't on the default branch.
+    NotMerged,
+    /// The branch is listed in the project's `persistent_branches` and can't be deleted at all.
+    Persistent,
+    /// Something else went wrong.
+    Error(String),
+}
+
+impl std::fmt::Display for WorktreeRemoveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WorktreeRemoveError::Changes => write!(f, "worktree has uncommitted changes"),
+            WorktreeRemoveError::NotMerged => {
+                write!(f, "branch has commits not on the default branch")
+            }
+            WorktreeRemoveError::Persistent => {
+                write!(f, "branch is persistent and cannot be deleted")
+            }
+            WorktreeRemoveError::Error(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for WorktreeRemoveError {}
+
+/// Delete a git worktree and its local branch.
+///
+/// Unless `force` is set, refuses to delete a worktree with uncommitted
+/// changes or a branch with commits that haven't been merged into the
+/// default branch, returning the specific reason so callers/TUI can prompt
+/// the user instead of silently discarding work.
+pub fn delete_worktree(
+    project: &Project,
+    branch: &str,
+    force: bool,
+) -> Result<(), WorktreeRemoveError> {
+    let config = GlobalConfig::load().map_err(|e| WorktreeRemoveError::Error(e.to_string()))?;
     let project_root = project.root_expanded();
 
     let branch_safe = branch.replace('/', "-");
@@ -271,9 +395,39 @@ pub fn delete_worktree(project: &Project, branch: &str) -> Result<()> {
         .join(&branch_safe);
 
     if !worktree_path.exists() {
-        anyhow::bail!("Worktree does not exist at {:?}", worktree_path);
+        return Err(WorktreeRemoveError::Error(format!(
+            "Worktree does not exist at {:?}",
+            worktree_path
+        )));
+    }
+
+    // Persistent branches are never deleted, even with `force`.
+    if project
+        .persistent_branches
+        .iter()
+        .any(|persistent| persistent == branch)
+    {
+        return Err(WorktreeRemoveError::Persistent);
+    }
+
+    if !force {
+        if is_worktree_dirty(&worktree_path)
+            .map_err(|e| WorktreeRemoveError::Error(e.to_string()))?
+        {
+            return Err(WorktreeRemoveError::Changes);
+        }
+
+        if has_unmerged_commits(&project_root, branch)
+            .map_err(|e| WorktreeRemoveError::Error(e.to_string()))?
+        {
+            return Err(WorktreeRemoveError::NotMerged);
+        }
     }
 
+    // Capture the branch tip before it's dropped, so `oplog::undo_last` can
+    // still restore it even after `branch -D` removes the ref.
+    let commit = rev_parse(&project_root, branch).ok();
+
     // Remove the worktree (suppress output to avoid breaking TUI)
     let output = Command::new("git")
         .current_dir(&project_root)
@@ -282,12 +436,16 @@ pub fn delete_worktree(project: &Project, branch: &str) -> Result<()> {
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
         .output()
-        .context("Failed to remove git worktree")?;
+        .map_err(|e| WorktreeRemoveError::Error(format!("Failed to remove git worktree: {}", e)))?;
 
     if !output.status.success() {
         // Try force removal of the directory
-        fs::remove_dir_all(&worktree_path)
-            .with_context(|| format!("Failed to remove worktree directory: {:?}", worktree_path))?;
+        fs::remove_dir_all(&worktree_path).map_err(|e| {
+            WorktreeRemoveError::Error(format!(
+                "Failed to remove worktree directory {:?}: {}",
+                worktree_path, e
+            ))
+        })?;
 
         // Prune worktree references
         Command::new("git")
@@ -300,11 +458,74 @@ pub fn delete_worktree(project: &Project, branch: &str) -> Result<()> {
     }
 
     // Delete the local branch
-    delete_local_branch(&project_root, branch)?;
+    delete_local_branch(&project_root, branch).map_err(|e| WorktreeRemoveError::Error(e.to_string()))?;
+
+    if let Some(commit) = commit {
+        if let Err(err) = crate::oplog::record_operation(&crate::oplog::Operation::WorktreeDeleted {
+            project: project.name.clone(),
+            branch: branch.to_string(),
+            path: worktree_path.clone(),
+            commit,
+        }) {
+            eprintln!("Warning: failed to record operation log entry: {}", err);
+        }
+    }
 
     Ok(())
 }
 
+/// Resolve `rev` to a commit SHA.
+fn rev_parse(repo_path: &Path, rev: &str) -> Result<String> {
+    let output = Command::new("git")
+        .current_dir(repo_path)
+        .args(["rev-parse", rev])
+        .output()
+        .context("Failed to run git rev-parse")?;
+
+    if !output.status.success() {
+        anyhow::bail!("git rev-parse '{}' failed", rev);
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Check whether a worktree has uncommitted changes.
+fn is_worktree_dirty(worktree_path: &Path) -> Result<bool> {
+    let output = Command::new("git")
+        .current_dir(worktree_path)
+        .args(["status", "--porcelain"])
+        .output()
+        .context("Failed to check worktree status")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("git status failed: {}", stderr.trim());
+    }
+
+    Ok(!output.stdout.is_empty())
+}
+
+/// Check whether `branch` has commits that aren't reachable from the default branch.
+fn has_unmerged_commits(repo_path: &Path, branch: &str) -> Result<bool> {
+    let default_branch = get_default_branch(repo_path)?;
+    if branch == default_branch {
+        return Ok(false);
+    }
+
+    let output = Command::new("git")
+        .current_dir(repo_path)
+        .args(["rev-list", branch, "--not", &default_branch])
+        .output()
+        .context("Failed to check for unmerged commits")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("git rev-list failed: {}", stderr.trim());
+    }
+
+    Ok(!output.stdout.is_empty())
+}
+
 /// Delete a local git branch
 fn delete_local_branch(repo_path: &Path, branch: &str) -> Result<()> {
     // Force delete the branch (-D) since the worktree is already removed
@@ -329,55 +550,216 @@ fn delete_local_branch(repo_path: &Path, branch: &str) -> Result<()> {
     Ok(())
 }
 
-/// List worktrees for a project
-pub fn list_worktrees(project: &Project) -> Result<Vec<WorktreeInfo>> {
-    let config = GlobalConfig::load()?;
-    let project_root = project.root_expanded();
+/// Why adopting an existing checkout/branch into a managed worktree was refused.
+#[derive(Debug)]
+pub enum ConversionError {
+    /// The checkout has uncommitted changes.
+    Changes,
+    /// Something else went wrong.
+    Error(String),
+}
+
+impl std::fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConversionError::Changes => write!(f, "checkout has uncommitted changes"),
+            ConversionError::Error(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for ConversionError {}
 
+/// Return the branch currently checked out at `repo_path`, or `None` if HEAD
+/// is detached.
+fn current_branch(repo_path: &Path) -> Result<Option<String>> {
     let output = Command::new("git")
-        .current_dir(&project_root)
-        .args(["worktree", "list", "--porcelain"])
+        .current_dir(repo_path)
+        .args(["symbolic-ref", "--short", "-q", "HEAD"])
         .output()
-        .context("Failed to list git worktrees")?;
+        .context("Failed to resolve current branch")?;
 
     if !output.status.success() {
-        return Ok(vec![]);
+        return Ok(None);
     }
 
-    let stdout = String::from_utf8(output.stdout)?;
-    let mut worktrees = Vec::new();
-    let mut current_path: Option<PathBuf> = None;
-    let mut current_branch: Option<String> = None;
+    Ok(Some(
+        String::from_utf8_lossy(&output.stdout).trim().to_string(),
+    ))
+}
 
-    let worktree_base = config.worktree_base_expanded().join(&project.name);
+/// Adopt a branch that's already checked out in the project's main checkout
+/// (rather than a worktree) by moving it under `{worktree_base}/{project}/{branch}`.
+///
+/// Modeled on the flow in `create_worktree`: refuses to touch a checkout with
+/// uncommitted changes, then applies the same `worktree.copy`/`worktree.symlink`
+/// provisioning as a freshly created worktree so the adopted one matches.
+pub fn convert_to_worktree(project: &Project, branch: &str) -> Result<PathBuf, ConversionError> {
+    let config = GlobalConfig::load().map_err(|e| ConversionError::Error(e.to_string()))?;
+    let project_root = project.root_expanded();
 
-    for line in stdout.lines() {
-        if line.starts_with("worktree ") {
-            // Save previous worktree if any
-            if let (Some(path), Some(branch)) = (current_path.take(), current_branch.take()) {
-                // Only include worktrees under our worktree_base
-                if path.starts_with(&worktree_base) {
-                    worktrees.push(WorktreeInfo { path, branch });
-                }
-            }
+    // `branch` may not be checked out in the main checkout at all (e.g. a
+    // stray local branch created elsewhere); only the checked-out case
+    // dirties/detaches `project_root`, so check against `branch` specifically.
+    let checked_out_branch =
+        current_branch(&project_root).map_err(|e| ConversionError::Error(e.to_string()))?;
+    let branch_is_checked_out = checked_out_branch.as_deref() == Some(branch);
+
+    let default_branch =
+        get_default_branch(&project_root).map_err(|e| ConversionError::Error(e.to_string()))?;
+
+    // The default branch is never detached from the main checkout (see
+    // below), so there's no way to also check it out into a worktree — git
+    // would just fail with its own "already checked out" error. Reject it
+    // up front with a clearer message instead.
+    if branch_is_checked_out && branch == default_branch {
+        return Err(ConversionError::Error(format!(
+            "Cannot convert '{}': it's the default branch checked out in the main checkout, \
+             which can't be detached to make room for a worktree",
+            branch
+        )));
+    }
+
+    if branch_is_checked_out
+        && is_worktree_dirty(&project_root).map_err(|e| ConversionError::Error(e.to_string()))?
+    {
+        return Err(ConversionError::Changes);
+    }
+
+    let branch_safe = branch.replace('/', "-");
+    let worktree_path = config
+        .worktree_base_expanded()
+        .join(&project.name)
+        .join(&branch_safe);
+
+    if worktree_path.exists() {
+        return Err(ConversionError::Error(format!(
+            "Worktree already exists at {:?}",
+            worktree_path
+        )));
+    }
 
-            current_path = Some(PathBuf::from(line.strip_prefix("worktree ").unwrap()));
-        } else if line.starts_with("branch ") {
-            let branch = line
-                .strip_prefix("branch refs/heads/")
-                .unwrap_or(line.strip_prefix("branch ").unwrap_or(""));
-            current_branch = Some(branch.to_string());
+    if let Some(parent) = worktree_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| {
+            ConversionError::Error(format!("Failed to create directory {:?}: {}", parent, e))
+        })?;
+    }
+
+    // `git worktree add` refuses to check out a branch that's already checked
+    // out elsewhere, including the main checkout itself, so detach it first —
+    // but only when `branch` is actually what's checked out there (we've
+    // already rejected the case where that's also the default branch above),
+    // and restore the default branch afterward so the main checkout isn't
+    // left detached.
+    if branch_is_checked_out && branch != default_branch {
+        let output = Command::new("git")
+            .current_dir(&project_root)
+            .args(["checkout", "--detach"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+            .map_err(|e| ConversionError::Error(format!("Failed to detach HEAD: {}", e)))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(ConversionError::Error(format!(
+                "Failed to detach HEAD in main checkout: {}",
+                stderr.trim()
+            )));
         }
     }
 
-    // Don't forget the last one
-    if let (Some(path), Some(branch)) = (current_path, current_branch) {
-        if path.starts_with(&worktree_base) {
-            worktrees.push(WorktreeInfo { path, branch });
+    let output = Command::new("git")
+        .current_dir(&project_root)
+        .args(["worktree", "add"])
+        .arg(&worktree_path)
+        .arg(branch)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|e| ConversionError::Error(format!("Failed to add worktree: {}", e)));
+
+    let restore_main_checkout = |error: Option<String>| -> ConversionError {
+        if branch_is_checked_out && branch != default_branch {
+            let restored = Command::new("git")
+                .current_dir(&project_root)
+                .args(["checkout", &default_branch])
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .status();
+            if !matches!(restored, Ok(status) if status.success()) {
+                eprintln!(
+                    "Warning: main checkout left on a detached HEAD; run 'git checkout {}' to restore it",
+                    default_branch
+                );
+            }
+        }
+        ConversionError::Error(error.unwrap_or_default())
+    };
+
+    let output = match output {
+        Ok(output) => output,
+        Err(e) => return Err(restore_main_checkout(Some(e.to_string()))),
+    };
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(restore_main_checkout(Some(format!(
+            "git worktree add failed: {}",
+            stderr.trim()
+        ))));
+    }
+
+    if branch_is_checked_out && branch != default_branch {
+        let output = Command::new("git")
+            .current_dir(&project_root)
+            .args(["checkout", &default_branch])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+            .map_err(|e| ConversionError::Error(format!("Failed to restore main checkout: {}", e)))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(ConversionError::Error(format!(
+                "Worktree created, but failed to restore main checkout to '{}': {}",
+                default_branch,
+                stderr.trim()
+            )));
         }
     }
 
-    Ok(worktrees)
+    crate::worktree::provision(project, &worktree_path)
+        .map_err(|e| ConversionError::Error(e.to_string()))?;
+
+    if let Err(err) = crate::oplog::record_operation(&crate::oplog::Operation::WorktreeCreated {
+        project: project.name.clone(),
+        branch: branch.to_string(),
+        path: worktree_path.clone(),
+        // convert_to_worktree always adopts a branch that already existed in
+        // the main checkout; undo must never delete it.
+        branch_created: false,
+    }) {
+        eprintln!("Warning: failed to record operation log entry: {}", err);
+    }
+
+    Ok(worktree_path)
+}
+
+/// List worktrees for a project
+pub fn list_worktrees(project: &Project) -> Result<Vec<WorktreeInfo>> {
+    let config = GlobalConfig::load()?;
+    let project_root = project.root_expanded();
+    let worktree_base = config.worktree_base_expanded().join(&project.name);
+
+    let worktrees = crate::git_backend::default_backend().list_worktrees(&project_root)?;
+
+    // Only include worktrees under our worktree_base; the repo may have
+    // other worktrees (or the main checkout) that twig doesn't manage.
+    Ok(worktrees
+        .into_iter()
+        .filter(|w| w.path.starts_with(&worktree_base))
+        .collect())
 }
 
 #[derive(Debug)]
@@ -475,54 +857,212 @@ pub fn merge_branch_to_default(repo_path: &Path, branch: &str) -> Result<()> {
         );
     }
 
+    let project_name = repo_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if let Err(err) = crate::oplog::record_operation(&crate::oplog::Operation::BranchMerged {
+        project: project_name,
+        branch: branch.to_string(),
+        default_branch,
+    }) {
+        eprintln!("Warning: failed to record operation log entry: {}", err);
+    }
+
     Ok(())
 }
 
-/// Copy a file or directory, preserving symlinks
-fn copy_path_preserve_symlinks(src: &Path, dst: &Path) -> Result<()> {
-    let metadata = fs::symlink_metadata(src)
-        .with_context(|| format!("Failed to read metadata for {:?}", src))?;
+/// How to bring a branch's commits into the default branch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntegrationStrategy {
+    Merge,
+    Rebase,
+    FastForwardOnly,
+}
+
+/// Integrate `branch` into the default branch using the given strategy.
+pub fn integrate_branch(
+    repo_path: &Path,
+    branch: &str,
+    strategy: IntegrationStrategy,
+) -> Result<()> {
+    match strategy {
+        IntegrationStrategy::Merge => merge_branch_to_default(repo_path, branch),
+        IntegrationStrategy::Rebase => rebase_branch_onto_default(repo_path, branch),
+        IntegrationStrategy::FastForwardOnly => fast_forward_branch_to_default(repo_path, branch),
+    }
+}
 
-    if metadata.file_type().is_symlink() {
-        let target = fs::read_link(src)
-            .with_context(|| format!("Failed to read symlink target for {:?}", src))?;
-        create_symlink(&target, dst)?;
-        return Ok(());
+/// Rebase `branch` onto the default branch, leaving `branch` checked out.
+///
+/// On conflict, the rebase is left halted (as git would leave it on the
+/// command line) and the conflicting files are reported so a caller can
+/// surface them, then either fix them up or call `abort_integration`.
+pub fn rebase_branch_onto_default(repo_path: &Path, branch: &str) -> Result<()> {
+    let default_branch = get_default_branch(repo_path)?;
+
+    let output = Command::new("git")
+        .current_dir(repo_path)
+        .args(["checkout", branch])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .context("Failed to checkout branch")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Failed to checkout '{}': {}", branch, stderr.trim());
     }
 
-    if metadata.is_dir() {
-        copy_dir_recursive(src, dst)?;
-    } else {
-        fs::copy(src, dst).with_context(|| format!("Failed to copy {:?} to {:?}", src, dst))?;
+    let output = Command::new("git")
+        .current_dir(repo_path)
+        .args(["rebase", &default_branch])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .context("Failed to rebase branch")?;
+
+    if !output.status.success() {
+        if rebase_in_progress(repo_path) {
+            let conflicts = conflicted_files(repo_path)?;
+            anyhow::bail!(
+                "Rebase of '{}' onto '{}' stopped with conflicts in: {}. Resolve them and continue, or call abort_integration.",
+                branch,
+                default_branch,
+                conflicts.join(", ")
+            );
+        }
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Rebase failed: {}", stderr.trim());
     }
 
     Ok(())
 }
 
-/// Recursively copy a directory, preserving symlinks
-fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<()> {
-    fs::create_dir_all(dst)?;
+/// Fast-forward the default branch to `branch`, failing cleanly if that isn't possible.
+pub fn fast_forward_branch_to_default(repo_path: &Path, branch: &str) -> Result<()> {
+    let default_branch = get_default_branch(repo_path)?;
+
+    let output = Command::new("git")
+        .current_dir(repo_path)
+        .args(["checkout", &default_branch])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .context("Failed to checkout default branch")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Failed to checkout '{}': {}", default_branch, stderr.trim());
+    }
 
-    for entry in fs::read_dir(src)? {
-        let entry = entry?;
-        let src_path = entry.path();
-        let dst_path = dst.join(entry.file_name());
+    let output = Command::new("git")
+        .current_dir(repo_path)
+        .args(["merge", "--ff-only", branch])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .context("Failed to fast-forward branch")?;
 
-        copy_path_preserve_symlinks(&src_path, &dst_path)?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!(
+            "'{}' cannot be fast-forwarded onto '{}': {}",
+            branch,
+            default_branch,
+            stderr.trim()
+        );
     }
 
     Ok(())
 }
 
-#[cfg(unix)]
-fn create_symlink(target: &Path, link: &Path) -> Result<()> {
-    use std::os::unix::fs::symlink;
+/// Abort a halted rebase or merge, whichever is in progress.
+pub fn abort_integration(repo_path: &Path) -> Result<()> {
+    if rebase_in_progress(repo_path) {
+        let output = Command::new("git")
+            .current_dir(repo_path)
+            .args(["rebase", "--abort"])
+            .output()
+            .context("Failed to abort rebase")?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!("git rebase --abort failed: {}", stderr.trim());
+        }
+
+        return Ok(());
+    }
+
+    if merge_in_progress(repo_path) {
+        let output = Command::new("git")
+            .current_dir(repo_path)
+            .args(["merge", "--abort"])
+            .output()
+            .context("Failed to abort merge")?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!("git merge --abort failed: {}", stderr.trim());
+        }
+
+        return Ok(());
+    }
+
+    anyhow::bail!("No merge or rebase is in progress to abort")
+}
+
+/// Resolve the repository's actual git directory (works for worktrees too).
+fn git_dir(repo_path: &Path) -> Result<PathBuf> {
+    let output = Command::new("git")
+        .current_dir(repo_path)
+        .args(["rev-parse", "--git-dir"])
+        .output()
+        .context("Failed to resolve git dir")?;
+
+    if !output.status.success() {
+        anyhow::bail!("Failed to resolve git dir for {:?}", repo_path);
+    }
+
+    let dir = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+    if dir.is_absolute() {
+        Ok(dir)
+    } else {
+        Ok(repo_path.join(dir))
+    }
+}
+
+fn rebase_in_progress(repo_path: &Path) -> bool {
+    match git_dir(repo_path) {
+        Ok(dir) => dir.join("rebase-merge").exists() || dir.join("rebase-apply").exists(),
+        Err(_) => false,
+    }
+}
 
-    symlink(target, link)
-        .with_context(|| format!("Failed to create symlink {:?} -> {:?}", link, target))
+fn merge_in_progress(repo_path: &Path) -> bool {
+    match git_dir(repo_path) {
+        Ok(dir) => dir.join("MERGE_HEAD").exists(),
+        Err(_) => false,
+    }
 }
 
-#[cfg(not(unix))]
-fn create_symlink(_target: &Path, _link: &Path) -> Result<()> {
-    anyhow::bail!("Symlink copying is only supported on Unix systems")
+/// List files with unresolved merge/rebase conflicts.
+fn conflicted_files(repo_path: &Path) -> Result<Vec<String>> {
+    let output = Command::new("git")
+        .current_dir(repo_path)
+        .args(["diff", "--name-only", "--diff-filter=U"])
+        .output()
+        .context("Failed to list conflicted files")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("git diff failed: {}", stderr.trim());
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| line.to_string())
+        .collect())
 }
+
@@ -0,0 +1,343 @@
+use anyhow::{Context, Result};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::Sender;
+
+use crate::git::{CommandOutput, WorktreeInfo};
+
+/// Git operations abstracted behind a trait so the CLI-shelling implementation
+/// can be swapped for a libgit2-backed one without touching callers.
+pub trait GitBackend {
+    fn create_worktree(&self, repo_path: &Path, worktree_path: &Path, branch: &str) -> Result<()>;
+    fn delete_worktree(&self, repo_path: &Path, worktree_path: &Path) -> Result<()>;
+    fn list_worktrees(&self, repo_path: &Path) -> Result<Vec<WorktreeInfo>>;
+    fn check_branch_exists(&self, repo_path: &Path, branch: &str) -> Result<bool>;
+    fn get_default_branch(&self, repo_path: &Path) -> Result<String>;
+
+    /// Merge `branch` into the default branch.
+    fn merge_branch(&self, repo_path: &Path, branch: &str) -> Result<()>;
+    /// Rebase `branch` onto the default branch, leaving `branch` checked out.
+    fn rebase_branch(&self, repo_path: &Path, branch: &str) -> Result<()>;
+
+    /// Fetch `origin`, reporting transfer progress over `progress` (if given)
+    /// as `CommandOutput::Line` messages so a caller can stream them to the TUI.
+    fn fetch_origin(&self, repo_path: &Path, progress: Option<&Sender<CommandOutput>>) -> Result<()>;
+}
+
+/// Pick the backend to use for git operations.
+///
+/// Defaults to `CliBackend`; set `TWIG_GIT_BACKEND=libgit2` to use
+/// `Git2Backend` instead (e.g. to avoid a `git` subprocess per call in a
+/// tight TUI refresh loop). `Git2Backend` falls back to the CLI for anything
+/// libgit2 doesn't support as cleanly, so this is safe to flip per-repo.
+pub fn default_backend() -> Box<dyn GitBackend> {
+    match std::env::var("TWIG_GIT_BACKEND").as_deref() {
+        Ok("libgit2") => Box::new(Git2Backend::new()),
+        _ => Box::new(CliBackend),
+    }
+}
+
+/// Shells out to the `git` binary. Works anywhere git works, at the cost of a
+/// process spawn and locale-dependent text parsing per call.
+pub struct CliBackend;
+
+impl GitBackend for CliBackend {
+    fn create_worktree(&self, repo_path: &Path, worktree_path: &Path, branch: &str) -> Result<()> {
+        let branch_exists = self.check_branch_exists(repo_path, branch)?;
+
+        let mut cmd = Command::new("git");
+        cmd.current_dir(repo_path);
+        cmd.arg("worktree").arg("add");
+
+        if branch_exists {
+            cmd.arg(worktree_path).arg(branch);
+        } else {
+            cmd.arg("-b").arg(branch).arg(worktree_path);
+        }
+
+        let output = cmd
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+            .context("Failed to create git worktree")?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!("git worktree add failed: {}", stderr.trim());
+        }
+
+        Ok(())
+    }
+
+    fn delete_worktree(&self, repo_path: &Path, worktree_path: &Path) -> Result<()> {
+        let output = Command::new("git")
+            .current_dir(repo_path)
+            .args(["worktree", "remove", "--force"])
+            .arg(worktree_path)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+            .context("Failed to remove git worktree")?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!("git worktree remove failed: {}", stderr.trim());
+        }
+
+        Ok(())
+    }
+
+    fn list_worktrees(&self, repo_path: &Path) -> Result<Vec<WorktreeInfo>> {
+        let output = Command::new("git")
+            .current_dir(repo_path)
+            .args(["worktree", "list", "--porcelain"])
+            .output()
+            .context("Failed to list git worktrees")?;
+
+        if !output.status.success() {
+            return Ok(vec![]);
+        }
+
+        let stdout = String::from_utf8(output.stdout)?;
+        Ok(parse_worktree_porcelain(&stdout))
+    }
+
+    fn check_branch_exists(&self, repo_path: &Path, branch: &str) -> Result<bool> {
+        let local = Command::new("git")
+            .current_dir(repo_path)
+            .args(["rev-parse", "--verify", branch])
+            .output()?;
+
+        if local.status.success() {
+            return Ok(true);
+        }
+
+        let remote = Command::new("git")
+            .current_dir(repo_path)
+            .args(["rev-parse", "--verify", &format!("origin/{}", branch)])
+            .output()?;
+
+        Ok(remote.status.success())
+    }
+
+    fn get_default_branch(&self, repo_path: &Path) -> Result<String> {
+        crate::git::get_default_branch(repo_path)
+    }
+
+    fn merge_branch(&self, repo_path: &Path, branch: &str) -> Result<()> {
+        crate::git::merge_branch_to_default(repo_path, branch)
+    }
+
+    fn rebase_branch(&self, repo_path: &Path, branch: &str) -> Result<()> {
+        crate::git::rebase_branch_onto_default(repo_path, branch)
+    }
+
+    fn fetch_origin(&self, repo_path: &Path, progress: Option<&Sender<CommandOutput>>) -> Result<()> {
+        let mut child = Command::new("git")
+            .current_dir(repo_path)
+            .args(["fetch", "origin", "--progress"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to start git fetch")?;
+
+        // git fetch writes its progress lines to stderr
+        if let Some(stderr) = child.stderr.take() {
+            let reader = BufReader::new(stderr);
+            for line in reader.lines().map_while(std::result::Result::ok) {
+                if let Some(tx) = progress {
+                    let _ = tx.send(CommandOutput::Line(line));
+                }
+            }
+        }
+
+        let status = child.wait().context("Failed to wait for git fetch")?;
+        if !status.success() {
+            anyhow::bail!("git fetch origin failed");
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_worktree_porcelain(stdout: &str) -> Vec<WorktreeInfo> {
+    let mut worktrees = Vec::new();
+    let mut current_path: Option<PathBuf> = None;
+    let mut current_branch: Option<String> = None;
+
+    for line in stdout.lines() {
+        if let Some(path) = line.strip_prefix("worktree ") {
+            if let (Some(path), Some(branch)) = (current_path.take(), current_branch.take()) {
+                worktrees.push(WorktreeInfo { path, branch });
+            }
+            current_path = Some(PathBuf::from(path));
+        } else if let Some(branch) = line.strip_prefix("branch ") {
+            let branch = branch.strip_prefix("refs/heads/").unwrap_or(branch);
+            current_branch = Some(branch.to_string());
+        }
+    }
+
+    if let (Some(path), Some(branch)) = (current_path, current_branch) {
+        worktrees.push(WorktreeInfo { path, branch });
+    }
+
+    worktrees
+}
+
+/// Talks to the repository directly through libgit2, avoiding a `git`
+/// subprocess per call. `list_worktrees` in particular benefits: instead of
+/// parsing `--porcelain` text, it enumerates `repo.worktrees()` and resolves
+/// each head directly.
+///
+/// Falls back to `CliBackend` for operations libgit2 doesn't support as
+/// cleanly as the CLI (worktree creation/removal, merge and rebase).
+pub struct Git2Backend {
+    fallback: CliBackend,
+}
+
+impl Git2Backend {
+    pub fn new() -> Self {
+        Self {
+            fallback: CliBackend,
+        }
+    }
+}
+
+impl Default for Git2Backend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GitBackend for Git2Backend {
+    fn create_worktree(&self, repo_path: &Path, worktree_path: &Path, branch: &str) -> Result<()> {
+        self.fallback.create_worktree(repo_path, worktree_path, branch)
+    }
+
+    fn delete_worktree(&self, repo_path: &Path, worktree_path: &Path) -> Result<()> {
+        self.fallback.delete_worktree(repo_path, worktree_path)
+    }
+
+    fn list_worktrees(&self, repo_path: &Path) -> Result<Vec<WorktreeInfo>> {
+        let repo = git2::Repository::open(repo_path)
+            .with_context(|| format!("Failed to open repository at {:?}", repo_path))?;
+
+        let mut worktrees = Vec::new();
+        for name in repo.worktrees()?.iter().flatten() {
+            let worktree = repo
+                .find_worktree(name)
+                .with_context(|| format!("Failed to resolve worktree '{}'", name))?;
+            let worktree_repo = git2::Repository::open_from_worktree(&worktree)
+                .with_context(|| format!("Failed to open worktree '{}'", name))?;
+
+            let branch = worktree_repo
+                .head()
+                .ok()
+                .and_then(|head| head.shorthand().map(str::to_string))
+                .unwrap_or_else(|| name.to_string());
+
+            worktrees.push(WorktreeInfo {
+                path: worktree.path().to_path_buf(),
+                branch,
+            });
+        }
+
+        Ok(worktrees)
+    }
+
+    fn check_branch_exists(&self, repo_path: &Path, branch: &str) -> Result<bool> {
+        let repo = git2::Repository::open(repo_path)
+            .with_context(|| format!("Failed to open repository at {:?}", repo_path))?;
+
+        if repo.find_branch(branch, git2::BranchType::Local).is_ok() {
+            return Ok(true);
+        }
+
+        let remote_branch = format!("origin/{}", branch);
+        Ok(repo
+            .find_branch(&remote_branch, git2::BranchType::Remote)
+            .is_ok())
+    }
+
+    fn get_default_branch(&self, repo_path: &Path) -> Result<String> {
+        let repo = git2::Repository::open(repo_path)
+            .with_context(|| format!("Failed to open repository at {:?}", repo_path))?;
+
+        if let Ok(reference) = repo.find_reference("refs/remotes/origin/HEAD") {
+            if let Some(target) = reference.symbolic_target() {
+                if let Some(name) = target.strip_prefix("refs/remotes/origin/") {
+                    return Ok(name.to_string());
+                }
+            }
+        }
+
+        for branch in ["main", "master"] {
+            if repo.find_branch(branch, git2::BranchType::Local).is_ok() {
+                return Ok(branch.to_string());
+            }
+        }
+
+        Ok("main".to_string())
+    }
+
+    // libgit2 exposes merge/rebase only as low-level index/rebase-operation
+    // APIs with no direct equivalent of `git merge`/`git rebase`'s conflict
+    // handling and working-tree checkout, so these just shell out via the
+    // fallback rather than reimplementing that machinery.
+    fn merge_branch(&self, repo_path: &Path, branch: &str) -> Result<()> {
+        self.fallback.merge_branch(repo_path, branch)
+    }
+
+    fn rebase_branch(&self, repo_path: &Path, branch: &str) -> Result<()> {
+        self.fallback.rebase_branch(repo_path, branch)
+    }
+
+    fn fetch_origin(&self, repo_path: &Path, progress: Option<&Sender<CommandOutput>>) -> Result<()> {
+        let repo = git2::Repository::open(repo_path)
+            .with_context(|| format!("Failed to open repository at {:?}", repo_path))?;
+        let mut remote = repo
+            .find_remote("origin")
+            .context("No 'origin' remote configured")?;
+
+        let mut callbacks = git2::RemoteCallbacks::new();
+        callbacks.credentials(|_url, username_from_url, allowed_types| {
+            if allowed_types.contains(git2::CredentialType::SSH_KEY) {
+                if let Some(username) = username_from_url {
+                    if let Ok(cred) = git2::Cred::ssh_key_from_agent(username) {
+                        return Ok(cred);
+                    }
+                }
+            }
+            if allowed_types.contains(git2::CredentialType::USER_PASS_PLAINTEXT) {
+                if let Ok(token) = std::env::var("TWIG_GIT_TOKEN") {
+                    return git2::Cred::userpass_plaintext("x-access-token", &token);
+                }
+            }
+            git2::Cred::default()
+        });
+
+        if let Some(tx) = progress {
+            let tx = tx.clone();
+            callbacks.transfer_progress(move |stats| {
+                let _ = tx.send(CommandOutput::Line(format!(
+                    "Receiving objects: {}/{} ({} bytes), {} local objects reused",
+                    stats.received_objects(),
+                    stats.total_objects(),
+                    stats.received_bytes(),
+                    stats.local_objects()
+                )));
+                true
+            });
+        }
+
+        let mut fetch_options = git2::FetchOptions::new();
+        fetch_options.remote_callbacks(callbacks);
+
+        remote
+            .fetch(&[] as &[&str], Some(&mut fetch_options), None)
+            .context("git2 fetch from origin failed")?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,199 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Command;
+
+use crate::config::{GlobalConfig, Project};
+
+/// A mutating worktree/branch action, recorded with enough state to reverse it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Operation {
+    WorktreeCreated {
+        project: String,
+        branch: String,
+        path: PathBuf,
+        /// Whether `branch` was newly created by this operation, as opposed
+        /// to an existing branch being checked out into a worktree (or
+        /// adopted by `convert_to_worktree`). Only set when true, since undo
+        /// must never delete a branch the user already had.
+        branch_created: bool,
+    },
+    WorktreeDeleted {
+        project: String,
+        branch: String,
+        path: PathBuf,
+        /// The branch tip, captured before `branch -D` dropped the ref.
+        commit: String,
+    },
+    BranchMerged {
+        project: String,
+        branch: String,
+        default_branch: String,
+    },
+}
+
+fn oplog_path() -> Result<PathBuf> {
+    let config = GlobalConfig::load()?;
+    Ok(config.state_dir_expanded().join("oplog.jsonl"))
+}
+
+/// Append an operation to the log.
+///
+/// Call this with the state captured *before* the destructive git call it
+/// describes (e.g. a branch's commit OID before `branch -D`), so the
+/// recorded entry is still enough to reverse the action afterwards.
+pub fn record_operation(op: &Operation) -> Result<()> {
+    let path = oplog_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create oplog directory: {:?}", parent))?;
+    }
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open oplog: {:?}", path))?;
+
+    let line = serde_json::to_string(op).context("Failed to serialize operation")?;
+    writeln!(file, "{}", line).with_context(|| format!("Failed to append to oplog: {:?}", path))?;
+
+    Ok(())
+}
+
+/// List recorded operations, oldest first.
+pub fn list_operations() -> Result<Vec<Operation>> {
+    let path = oplog_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read oplog: {:?}", path))?;
+
+    content
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            serde_json::from_str(line)
+                .with_context(|| format!("Failed to parse oplog entry: {}", line))
+        })
+        .collect()
+}
+
+/// Undo the most recently recorded operation and drop it from the log.
+pub fn undo_last() -> Result<Operation> {
+    let mut ops = list_operations()?;
+    let last = ops
+        .pop()
+        .ok_or_else(|| anyhow::anyhow!("No operations to undo"))?;
+
+    match &last {
+        Operation::WorktreeCreated {
+            project,
+            branch,
+            path,
+            branch_created,
+        } => {
+            let proj = Project::load(project)?;
+            let project_root = proj.root_expanded();
+
+            if path.exists() {
+                let output = Command::new("git")
+                    .current_dir(&project_root)
+                    .args(["worktree", "remove", "--force"])
+                    .arg(path)
+                    .output()
+                    .context("Failed to remove git worktree")?;
+
+                if !output.status.success() {
+                    // Fall back to a plain directory removal, then prune the
+                    // now-stale worktree admin entry, same as `delete_worktree`.
+                    fs::remove_dir_all(path)
+                        .with_context(|| format!("Failed to remove worktree: {:?}", path))?;
+
+                    Command::new("git")
+                        .current_dir(&project_root)
+                        .args(["worktree", "prune"])
+                        .status()
+                        .ok();
+                }
+            }
+
+            // Only drop the branch if twig created it. A branch the user
+            // already had (an adopted or pre-existing branch) is exactly the
+            // data the oplog exists to protect, not undo away.
+            if *branch_created {
+                let status = Command::new("git")
+                    .current_dir(&project_root)
+                    .args(["branch", "-D", branch])
+                    .output()
+                    .context("Failed to delete branch")?;
+                if !status.status.success() {
+                    let stderr = String::from_utf8_lossy(&status.stderr);
+                    if !stderr.contains("not found") {
+                        anyhow::bail!("Failed to delete branch '{}': {}", branch, stderr.trim());
+                    }
+                }
+            }
+        }
+        Operation::WorktreeDeleted {
+            project,
+            branch,
+            path,
+            commit,
+        } => {
+            let proj = Project::load(project)?;
+            let project_root = proj.root_expanded();
+
+            let status = Command::new("git")
+                .current_dir(&project_root)
+                .args(["branch", branch, commit])
+                .status()
+                .context("Failed to recreate branch")?;
+            if !status.success() {
+                anyhow::bail!("Failed to recreate branch '{}' at {}", branch, commit);
+            }
+
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed to create directory: {:?}", parent))?;
+            }
+
+            let output = Command::new("git")
+                .current_dir(&project_root)
+                .args(["worktree", "add"])
+                .arg(path)
+                .arg(branch)
+                .output()
+                .context("Failed to recreate worktree")?;
+            if !output.status.success() {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                anyhow::bail!(
+                    "Failed to recreate worktree at {:?}: {}",
+                    path,
+                    stderr.trim()
+                );
+            }
+        }
+        Operation::BranchMerged { .. } => {
+            anyhow::bail!("Undoing a merge is not supported; revert the merge commit manually");
+        }
+    }
+
+    rewrite_log(&ops)?;
+    Ok(last)
+}
+
+fn rewrite_log(ops: &[Operation]) -> Result<()> {
+    let path = oplog_path()?;
+    let mut content = String::new();
+    for op in ops {
+        content.push_str(&serde_json::to_string(op).context("Failed to serialize operation")?);
+        content.push('\n');
+    }
+    fs::write(&path, content).with_context(|| format!("Failed to rewrite oplog: {:?}", path))
+}
@@ -1,12 +1,65 @@
+use std::collections::{HashSet, VecDeque};
 use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
 use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::time::{Duration, SystemTime};
 
 use anyhow::{Context, Result};
 
+/// A window to materialize in a tmux session, along with its panes.
+#[derive(Debug, Clone)]
+pub struct WindowSpec {
+    pub name: String,
+    pub panes: Vec<PaneSpec>,
+}
+
+/// A single pane within a `WindowSpec`, with an optional startup command.
+#[derive(Debug, Clone, Default)]
+pub struct PaneSpec {
+    pub command: Option<String>,
+}
+
+/// An asynchronous notification pushed by tmux outside of a command's reply,
+/// e.g. a window created or closed by another client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControlEvent {
+    WindowAdd { window_id: String },
+    WindowClose { window_id: String },
+    LayoutChange { window_id: String, layout: String },
+    SessionChanged { session_id: String, session_name: String },
+    Output { pane_id: String, data: String },
+    ClientDetached { client_name: String },
+    /// Any notification this client doesn't decode specifically, kept as raw name/args.
+    Other { name: String, args: String },
+}
+
+/// A tmux session as reported by `list-sessions`.
+#[derive(Debug, Clone)]
+pub struct SessionInfo {
+    pub name: String,
+    pub created: SystemTime,
+    pub last_attached: Option<SystemTime>,
+    pub attached: bool,
+}
+
+/// A tmux window as reported by `list-windows`.
+#[derive(Debug, Clone)]
+pub struct WindowInfo {
+    pub index: u32,
+    pub name: String,
+    pub active: bool,
+}
+
 pub struct ControlClient {
     child: Child,
     stdin: ChildStdin,
     stdout: BufReader<ChildStdout>,
+    pending_events: VecDeque<ControlEvent>,
+    /// A notification line read up to (but not including) its trailing `\n`
+    /// by a non-blocking `poll_events` read that ran out of buffered data
+    /// mid-line. Prepended to the next line read, whether by `poll_events`
+    /// or `command`.
+    partial_line: String,
 }
 
 impl ControlClient {
@@ -37,6 +90,8 @@ impl ControlClient {
             child,
             stdin,
             stdout: BufReader::new(stdout),
+            pending_events: VecDeque::new(),
+            partial_line: String::new(),
         })
     }
 
@@ -65,9 +120,74 @@ impl ControlClient {
             child,
             stdin,
             stdout: BufReader::new(stdout),
+            pending_events: VecDeque::new(),
+            partial_line: String::new(),
         })
     }
 
+    /// Drain notifications tmux has sent since the last call to `command` or `poll_events`.
+    ///
+    /// Events seen interleaved inside a command's `%begin`/`%end` block are
+    /// buffered there and surfaced here too. On top of that, this does its
+    /// own non-blocking read of stdout, so notifications that arrive while
+    /// idle (no `command` call in flight) are picked up immediately rather
+    /// than waiting for the next command's reply to read past them.
+    pub fn poll_events(&mut self) -> Vec<ControlEvent> {
+        self.read_available_notifications();
+        self.pending_events.drain(..).collect()
+    }
+
+    /// Read whatever tmux has already written to stdout without blocking,
+    /// decoding any complete notification lines found. A line still pending
+    /// a trailing `\n` is stashed in `partial_line` and completed on the next
+    /// read (here or in `command`).
+    #[cfg(unix)]
+    fn read_available_notifications(&mut self) {
+        use std::os::unix::io::AsRawFd;
+
+        let fd = self.stdout.get_ref().as_raw_fd();
+        let Ok(orig_flags) = set_nonblocking(fd, true) else {
+            return;
+        };
+
+        loop {
+            let mut chunk = String::new();
+            let result = self.stdout.read_line(&mut chunk);
+            // `read_line` may have appended a partial line before hitting
+            // WouldBlock (or any other error) on a later read, so always
+            // keep what it gave us.
+            self.partial_line.push_str(&chunk);
+
+            match result {
+                Ok(0) => break,
+                Ok(_) => {
+                    if self.partial_line.ends_with('\n') {
+                        let trimmed = self
+                            .partial_line
+                            .trim_end_matches(['\r', '\n'])
+                            .to_string();
+                        self.partial_line.clear();
+                        if trimmed.starts_with('%')
+                            && !trimmed.starts_with("%begin")
+                            && !trimmed.starts_with("%end")
+                            && !trimmed.starts_with("%error")
+                            && !trimmed.starts_with("%exit")
+                        {
+                            self.pending_events.push_back(decode_event(&trimmed));
+                        }
+                    }
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => break,
+                Err(_) => break,
+            }
+        }
+
+        let _ = restore_flags(fd, orig_flags);
+    }
+
+    #[cfg(not(unix))]
+    fn read_available_notifications(&mut self) {}
+
     pub fn command(&mut self, cmd: &str) -> Result<Vec<String>> {
         if debug_enabled() {
             eprintln!("[tmux-control] >> {}", cmd);
@@ -81,7 +201,9 @@ impl ControlClient {
         let mut command_id: Option<u64> = None;
 
         loop {
-            let mut line = String::new();
+            // Pick up where a non-blocking `poll_events` read left off, if it
+            // stopped mid-line.
+            let mut line = std::mem::take(&mut self.partial_line);
             let bytes = self
                 .stdout
                 .read_line(&mut line)
@@ -121,11 +243,12 @@ impl ControlClient {
                 continue;
             }
 
-            if command_id.is_none() {
+            if trimmed.starts_with('%') {
+                self.pending_events.push_back(decode_event(trimmed));
                 continue;
             }
 
-            if trimmed.starts_with('%') {
+            if command_id.is_none() {
                 continue;
             }
 
@@ -135,16 +258,187 @@ impl ControlClient {
         Ok(output)
     }
 
-    pub fn new_window(&mut self, session: &str, name: &str, cwd: &std::path::Path) -> Result<()> {
+    /// List the tmux server's existing sessions.
+    pub fn list_sessions(&mut self) -> Result<Vec<SessionInfo>> {
+        let format = "#S\t#{session_created}\t#{?session_last_attached,#{session_last_attached},}\t#{session_attached}";
+        let lines = self.command(&format!("list-sessions -F {}", quote_tmux_arg(format)))?;
+        lines.iter().map(|line| parse_session_line(line)).collect()
+    }
+
+    /// List the windows of a session.
+    pub fn list_windows(&mut self, session: &str) -> Result<Vec<WindowInfo>> {
+        let format = "#{window_index}\t#{window_name}\t#{window_active}";
         let command = format!(
-            "new-window -d -t {} -n {} -c {}",
+            "list-windows -t {} -F {}",
+            quote_tmux_arg(session),
+            quote_tmux_arg(format)
+        );
+        let lines = self.command(&command)?;
+        lines.iter().map(|line| parse_window_line(line)).collect()
+    }
+
+    /// Create a window and return the `pane_id` of its initial pane.
+    pub fn new_window(&mut self, session: &str, name: &str, cwd: &std::path::Path) -> Result<String> {
+        let command = format!(
+            "new-window -d -P -F {} -t {} -n {} -c {}",
+            quote_tmux_arg("#{pane_id}"),
             quote_tmux_arg(session),
             quote_tmux_arg(name),
             quote_tmux_arg(&cwd.to_string_lossy())
         );
+        let output = self.command(&command)?;
+        output
+            .first()
+            .map(|line| line.trim().to_string())
+            .ok_or_else(|| anyhow::anyhow!("new-window did not report a pane id"))
+    }
+
+    /// Split the active pane of `window` and return the `pane_id` of the new pane.
+    ///
+    /// tmux inserts the new pane at the position after the one it split, which
+    /// renumbers later panes by index — so the `pane_id` returned here (not
+    /// its post-split index) is what callers must address it by.
+    pub fn split_window(&mut self, session: &str, window: &str, cwd: &Path) -> Result<String> {
+        let command = format!(
+            "split-window -d -P -F {} -t {}:{} -c {}",
+            quote_tmux_arg("#{pane_id}"),
+            quote_tmux_arg(session),
+            quote_tmux_arg(window),
+            quote_tmux_arg(&cwd.to_string_lossy())
+        );
+        let output = self.command(&command)?;
+        output
+            .first()
+            .map(|line| line.trim().to_string())
+            .ok_or_else(|| anyhow::anyhow!("split-window did not report a pane id"))
+    }
+
+    /// Send a command line to a specific pane (addressed by `pane_id`, e.g.
+    /// `%3` as returned by `new_window`/`split_window`), as if typed and
+    /// followed by Enter.
+    pub fn send_keys(&mut self, pane_id: &str, keys: &str) -> Result<()> {
+        let command = format!(
+            "send-keys -t {} {} Enter",
+            quote_tmux_arg(pane_id),
+            quote_tmux_arg(keys)
+        );
+        self.command(&command)?;
+        Ok(())
+    }
+
+    /// Apply a tmux layout (e.g. `tiled`, `even-horizontal`) to a window.
+    pub fn select_layout(&mut self, session: &str, window: &str, layout: &str) -> Result<()> {
+        let target = format!("{}:{}", session, window);
+        let command = format!("select-layout -t {} {}", quote_tmux_arg(&target), layout);
         self.command(&command)?;
         Ok(())
     }
+
+    /// Realize the declared windows/panes of a project in `session`, creating
+    /// only the windows that don't already exist so re-running `start` on a
+    /// live session is a no-op for anything already built.
+    pub fn materialize_session(
+        &mut self,
+        session: &str,
+        cwd: &Path,
+        windows: &[WindowSpec],
+    ) -> Result<()> {
+        let existing: HashSet<String> = self
+            .list_windows(session)?
+            .into_iter()
+            .map(|w| w.name)
+            .collect();
+
+        for window in windows {
+            if existing.contains(&window.name) {
+                continue;
+            }
+
+            let first_pane_id = self.new_window(session, &window.name, cwd)?;
+
+            if let Some(first) = window.panes.first() {
+                if let Some(command) = &first.command {
+                    self.send_keys(&first_pane_id, command)?;
+                }
+            }
+
+            for pane in window.panes.iter().skip(1) {
+                let pane_id = self.split_window(session, &window.name, cwd)?;
+                if let Some(command) = &pane.command {
+                    self.send_keys(&pane_id, command)?;
+                }
+            }
+
+            if window.panes.len() > 1 {
+                self.select_layout(session, &window.name, "tiled")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse a `#S\t#{session_created}\t#{?session_last_attached,#{session_last_attached},}\t#{session_attached}` line.
+fn parse_session_line(line: &str) -> Result<SessionInfo> {
+    let mut parts = line.splitn(4, '\t');
+    let name = parts
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("Malformed session line: {}", line))?
+        .to_string();
+    let created_raw = parts
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("Malformed session line: {}", line))?;
+    let created = parse_unix_time(created_raw)?;
+    let last_attached_raw = parts
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("Malformed session line: {}", line))?;
+    let last_attached = if last_attached_raw.is_empty() {
+        None
+    } else {
+        Some(parse_unix_time(last_attached_raw)?)
+    };
+    // `#{session_attached}` is tmux's live client count for the session, not
+    // a boolean, so any non-zero value means at least one client is attached
+    // right now. `session_last_attached` only records that *some* client
+    // attached at some point and stays set after everyone detaches, so it's
+    // not a substitute for "currently attached".
+    let attached_raw = parts.next().unwrap_or("0");
+    let attached = attached_raw.parse::<u32>().unwrap_or(0) > 0;
+
+    Ok(SessionInfo {
+        name,
+        created,
+        attached,
+        last_attached,
+    })
+}
+
+/// Parse a `#{window_index}\t#{window_name}\t#{window_active}` line.
+fn parse_window_line(line: &str) -> Result<WindowInfo> {
+    let mut parts = line.splitn(3, '\t');
+    let index = parts
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("Malformed window line: {}", line))?
+        .parse::<u32>()
+        .with_context(|| format!("Invalid window index in: {}", line))?;
+    let name = parts
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("Malformed window line: {}", line))?
+        .to_string();
+    let active = parts.next().unwrap_or("0") == "1";
+
+    Ok(WindowInfo {
+        index,
+        name,
+        active,
+    })
+}
+
+fn parse_unix_time(raw: &str) -> Result<SystemTime> {
+    let secs = raw
+        .parse::<u64>()
+        .with_context(|| format!("Invalid tmux timestamp: {}", raw))?;
+    Ok(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
 }
 
 fn quote_tmux_arg(value: &str) -> String {
@@ -156,6 +450,80 @@ fn debug_enabled() -> bool {
     std::env::var_os("TWIG_TMUX_DEBUG").is_some()
 }
 
+/// Set (or clear) `O_NONBLOCK` on `fd`, returning the flags it had before the
+/// change so the caller can restore them afterward.
+#[cfg(unix)]
+fn set_nonblocking(fd: std::os::unix::io::RawFd, nonblocking: bool) -> std::io::Result<libc::c_int> {
+    let orig = unsafe { libc::fcntl(fd, libc::F_GETFL) };
+    if orig < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    let new_flags = if nonblocking {
+        orig | libc::O_NONBLOCK
+    } else {
+        orig & !libc::O_NONBLOCK
+    };
+
+    if unsafe { libc::fcntl(fd, libc::F_SETFL, new_flags) } < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok(orig)
+}
+
+#[cfg(unix)]
+fn restore_flags(fd: std::os::unix::io::RawFd, flags: libc::c_int) -> std::io::Result<()> {
+    if unsafe { libc::fcntl(fd, libc::F_SETFL, flags) } < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Decode a `%`-prefixed notification line into a `ControlEvent`.
+fn decode_event(line: &str) -> ControlEvent {
+    let mut parts = line.splitn(2, ' ');
+    let name = parts.next().unwrap_or_default();
+    let rest = parts.next().unwrap_or("");
+
+    match name {
+        "%window-add" => ControlEvent::WindowAdd {
+            window_id: rest.to_string(),
+        },
+        "%window-close" => ControlEvent::WindowClose {
+            window_id: rest.to_string(),
+        },
+        "%layout-change" => {
+            let mut fields = rest.splitn(2, ' ');
+            let window_id = fields.next().unwrap_or_default().to_string();
+            let layout = fields.next().unwrap_or_default().to_string();
+            ControlEvent::LayoutChange { window_id, layout }
+        }
+        "%session-changed" => {
+            let mut fields = rest.splitn(2, ' ');
+            let session_id = fields.next().unwrap_or_default().to_string();
+            let session_name = fields.next().unwrap_or_default().to_string();
+            ControlEvent::SessionChanged {
+                session_id,
+                session_name,
+            }
+        }
+        "%output" => {
+            let mut fields = rest.splitn(2, ' ');
+            let pane_id = fields.next().unwrap_or_default().to_string();
+            let data = fields.next().unwrap_or_default().to_string();
+            ControlEvent::Output { pane_id, data }
+        }
+        "%client-detached" => ControlEvent::ClientDetached {
+            client_name: rest.to_string(),
+        },
+        _ => ControlEvent::Other {
+            name: name.to_string(),
+            args: rest.to_string(),
+        },
+    }
+}
+
 fn parse_command_id(line: &str) -> Result<u64> {
     let mut parts = line.split_whitespace();
     let prefix = parts.next().unwrap_or_default();
@@ -268,6 +636,123 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_materialize_session_is_idempotent() {
+        if !tmux_available() {
+            eprintln!("tmux not available, skipping control mode test");
+            return;
+        }
+
+        let server = unique_server_name();
+        let _guard = ServerGuard::new(server.clone());
+        let session = "twig_test_materialize";
+
+        let mut client = match ControlClient::connect(Some(&server)) {
+            Ok(client) => client,
+            Err(err) => {
+                eprintln!("tmux control client unavailable: {err}");
+                return;
+            }
+        };
+
+        if let Err(err) = client.command(&format!("new-session -d -s {}", session)) {
+            eprintln!("failed to create test session: {err}");
+            let _ = client.command("kill-server");
+            return;
+        }
+
+        let windows = vec![WindowSpec {
+            name: "editor".to_string(),
+            panes: vec![PaneSpec {
+                command: Some("true".to_string()),
+            }],
+        }];
+
+        for _ in 0..2 {
+            if let Err(err) =
+                client.materialize_session(session, std::path::Path::new("/"), &windows)
+            {
+                eprintln!("failed to materialize session: {err}");
+                let _ = client.command("kill-server");
+                return;
+            }
+        }
+
+        let result = client.list_windows(session).expect("list_windows failed");
+        assert_eq!(
+            result.iter().filter(|w| w.name == "editor").count(),
+            1,
+            "expected exactly one 'editor' window after repeated materialize, got {:?}",
+            result
+        );
+    }
+
+    #[test]
+    fn test_parse_session_line() {
+        let session = parse_session_line("main\t1700000000\t1700000500\t1").unwrap();
+        assert_eq!(session.name, "main");
+        assert!(session.attached);
+        assert_eq!(
+            session.last_attached,
+            Some(UNIX_EPOCH + std::time::Duration::from_secs(1700000500))
+        );
+
+        let never_attached = parse_session_line("scratch\t1700000000\t\t0").unwrap();
+        assert!(!never_attached.attached);
+        assert_eq!(never_attached.last_attached, None);
+
+        // Attached in the past but detached now: session_last_attached is
+        // still set, but session_attached (live client count) is 0.
+        let previously_attached =
+            parse_session_line("stale\t1700000000\t1700000500\t0").unwrap();
+        assert!(!previously_attached.attached);
+        assert_eq!(
+            previously_attached.last_attached,
+            Some(UNIX_EPOCH + std::time::Duration::from_secs(1700000500))
+        );
+    }
+
+    #[test]
+    fn test_parse_window_line() {
+        let window = parse_window_line("0\teditor\t1").unwrap();
+        assert_eq!(window.index, 0);
+        assert_eq!(window.name, "editor");
+        assert!(window.active);
+
+        let inactive = parse_window_line("1\tshell\t0").unwrap();
+        assert!(!inactive.active);
+    }
+
+    #[test]
+    fn test_decode_event() {
+        assert_eq!(
+            decode_event("%window-add @3"),
+            ControlEvent::WindowAdd {
+                window_id: "@3".to_string()
+            }
+        );
+        assert_eq!(
+            decode_event("%session-changed $1 main"),
+            ControlEvent::SessionChanged {
+                session_id: "$1".to_string(),
+                session_name: "main".to_string(),
+            }
+        );
+        assert_eq!(
+            decode_event("%client-detached /dev/pts/3"),
+            ControlEvent::ClientDetached {
+                client_name: "/dev/pts/3".to_string()
+            }
+        );
+        match decode_event("%unknown-event some args") {
+            ControlEvent::Other { name, args } => {
+                assert_eq!(name, "%unknown-event");
+                assert_eq!(args, "some args");
+            }
+            other => panic!("expected Other, got {:?}", other),
+        }
+    }
+
     struct ServerGuard {
         name: String,
     }
@@ -0,0 +1,110 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+
+use crate::config::Project;
+
+/// Copy and symlink the project's configured `worktree.copy`/`worktree.symlink`
+/// files into a freshly created worktree.
+///
+/// A configured file that doesn't exist yet in the main checkout (e.g. a
+/// template `.env` nobody has created) is skipped with a warning rather than
+/// aborting the whole provisioning step.
+pub fn provision(project: &Project, worktree_path: &Path) -> Result<()> {
+    let Some(wt_config) = &project.worktree else {
+        return Ok(());
+    };
+
+    let project_root = project.root_expanded();
+
+    for file in &wt_config.copy {
+        let src = project_root.join(file);
+        if !src.exists() {
+            eprintln!(
+                "Warning: skipping copy of '{}', not found in {:?}",
+                file, project_root
+            );
+            continue;
+        }
+
+        let dst = worktree_path.join(file);
+        if let Some(parent) = dst.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory for '{}'", file))?;
+        }
+
+        copy_path_preserve_symlinks(&src, &dst)
+            .with_context(|| format!("Failed to copy '{}' into worktree", file))?;
+    }
+
+    for file in &wt_config.symlink {
+        let src = project_root.join(file);
+        if !src.exists() {
+            eprintln!(
+                "Warning: skipping symlink of '{}', not found in {:?}",
+                file, project_root
+            );
+            continue;
+        }
+
+        let dst = worktree_path.join(file);
+        if let Some(parent) = dst.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory for '{}'", file))?;
+        }
+
+        create_symlink(&src, &dst)
+            .with_context(|| format!("Failed to symlink '{}' into worktree", file))?;
+    }
+
+    Ok(())
+}
+
+/// Copy a file or directory, preserving symlinks
+fn copy_path_preserve_symlinks(src: &Path, dst: &Path) -> Result<()> {
+    let metadata = fs::symlink_metadata(src)
+        .with_context(|| format!("Failed to read metadata for {:?}", src))?;
+
+    if metadata.file_type().is_symlink() {
+        let target = fs::read_link(src)
+            .with_context(|| format!("Failed to read symlink target for {:?}", src))?;
+        create_symlink(&target, dst)?;
+        return Ok(());
+    }
+
+    if metadata.is_dir() {
+        copy_dir_recursive(src, dst)?;
+    } else {
+        fs::copy(src, dst).with_context(|| format!("Failed to copy {:?} to {:?}", src, dst))?;
+    }
+
+    Ok(())
+}
+
+/// Recursively copy a directory, preserving symlinks
+fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<()> {
+    fs::create_dir_all(dst)?;
+
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let src_path = entry.path();
+        let dst_path = dst.join(entry.file_name());
+
+        copy_path_preserve_symlinks(&src_path, &dst_path)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn create_symlink(target: &Path, link: &Path) -> Result<()> {
+    use std::os::unix::fs::symlink;
+
+    symlink(target, link)
+        .with_context(|| format!("Failed to create symlink {:?} -> {:?}", link, target))
+}
+
+#[cfg(not(unix))]
+fn create_symlink(_target: &Path, _link: &Path) -> Result<()> {
+    anyhow::bail!("Symlink copying is only supported on Unix systems")
+}